@@ -0,0 +1,163 @@
+use std::{future::Future, marker::PhantomData, pin::Pin, task::{Context, Poll}};
+
+use tower::Service;
+
+use crate::{error::Error, request::JobRequest};
+
+/// Types that can be pulled out of a [`JobRequest`] so they can be passed as
+/// arguments to a job handler.
+///
+/// This is the job-queue analogue of axum's `FromRequestParts`: the framework
+/// resolves every extractor argument from the request context before invoking
+/// the handler, so a handler can simply declare the state it needs
+///
+/// ```rust,no_run
+/// # use std::sync::Arc;
+/// # use apalis_core::layers::extensions::Extension;
+/// # struct Db;
+/// # struct MyJob;
+/// async fn handler(job: MyJob, state: Extension<Arc<Db>>) {
+///     // `state` was cloned out of the job context for us
+/// }
+/// ```
+pub trait FromJobRequest<J>: Sized {
+    /// Extract the value from the context of `req`, or reject with [`Error`].
+    fn from_job_request(req: &JobRequest<J>) -> Result<Self, Error>;
+}
+
+/// Build a job [`Service`] from an async function of the job payload plus any
+/// number of [`FromJobRequest`] extractors.
+///
+/// Mirrors axum's `handler` adapters: the returned service decomposes each
+/// incoming [`JobRequest`] into its payload and the resolved extractors before
+/// calling `f`. Extraction failures short-circuit into the service error.
+pub fn job_fn<F, J, Args>(f: F) -> JobFn<F, J, Args>
+where
+    F: Handler<J, Args>,
+{
+    JobFn {
+        f,
+        _marker: PhantomData,
+    }
+}
+
+/// The [`Service`] produced by [`job_fn`].
+pub struct JobFn<F, J, Args> {
+    f: F,
+    _marker: PhantomData<fn(J, Args)>,
+}
+
+impl<F: Clone, J, Args> Clone for JobFn<F, J, Args> {
+    fn clone(&self) -> Self {
+        Self {
+            f: self.f.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, J, Args> std::fmt::Debug for JobFn<F, J, Args> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobFn").finish_non_exhaustive()
+    }
+}
+
+impl<F, J, Args> Service<JobRequest<J>> for JobFn<F, J, Args>
+where
+    F: Handler<J, Args> + Clone,
+{
+    type Response = F::Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: JobRequest<J>) -> Self::Future {
+        self.f.clone().call(req)
+    }
+}
+
+/// Async functions that can service a [`JobRequest`] by consuming the payload
+/// and a tuple of [`FromJobRequest`] extractors.
+///
+/// Implemented for functions of arity 1 (just the job) through a handful of
+/// extractor arguments, the same way axum derives its handler impls.
+pub trait Handler<J, Args>: Clone + Send + Sized + 'static {
+    /// The value produced by the handler future.
+    type Response: Send + 'static;
+
+    /// Resolve the extractors from `req`, then run the handler.
+    fn call(
+        self,
+        req: JobRequest<J>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Response, Error>> + Send>>;
+}
+
+macro_rules! impl_handler {
+    ( $( $ty:ident ),* ) => {
+        #[allow(non_snake_case, unused_variables)]
+        impl<F, Fut, J, Res, $( $ty, )*> Handler<J, ( $( $ty, )* )> for F
+        where
+            F: FnOnce(J, $( $ty, )*) -> Fut + Clone + Send + 'static,
+            Fut: Future<Output = Res> + Send + 'static,
+            J: Send + 'static,
+            Res: Send + 'static,
+            $( $ty: FromJobRequest<J> + Send + 'static, )*
+        {
+            type Response = Res;
+
+            fn call(
+                self,
+                req: JobRequest<J>,
+            ) -> Pin<Box<dyn Future<Output = Result<Self::Response, Error>> + Send>> {
+                // resolve every extractor before taking ownership of the payload
+                $(
+                    let $ty = match $ty::from_job_request(&req) {
+                        Ok(value) => value,
+                        Err(err) => return Box::pin(async move { Err(err) }),
+                    };
+                )*
+                let job = req.into_inner();
+                Box::pin(async move { Ok(self(job, $( $ty, )*).await) })
+            }
+        }
+    };
+}
+
+impl_handler!();
+impl_handler!(T1);
+impl_handler!(T1, T2);
+impl_handler!(T1, T2, T3);
+impl_handler!(T1, T2, T3, T4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::extensions::Extension;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn job_fn_resolves_extractor_arguments() {
+        async fn handler(job: u32, state: Extension<u32>) -> u32 {
+            job + state.0
+        }
+
+        let mut req = JobRequest::new(2u32);
+        req.context_mut().insert(40u32);
+
+        let out = job_fn(handler).oneshot(req).await.unwrap();
+        assert_eq!(out, 42);
+    }
+
+    #[tokio::test]
+    async fn job_fn_rejects_a_missing_extractor() {
+        async fn handler(_job: u32, _state: Extension<u32>) {}
+
+        let req = JobRequest::new(1u32);
+
+        let err = job_fn(handler).oneshot(req).await.unwrap_err();
+        assert!(matches!(err, Error::MissingData(_)));
+    }
+}
@@ -1,7 +1,7 @@
 use std::task::{Context, Poll};
 use tower::Service;
 
-use crate::request::JobRequest;
+use crate::{error::Error, extract::FromJobRequest, request::JobRequest};
 
 /// Extractor and response for extensions.
 ///
@@ -63,6 +63,24 @@ use crate::request::JobRequest;
 #[derive(Debug, Clone, Copy)]
 pub struct Extension<T>(pub T);
 
+impl<J, T> FromJobRequest<J> for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn from_job_request(req: &JobRequest<J>) -> Result<Self, Error> {
+        req.context()
+            .get::<T>()
+            .cloned()
+            .map(Extension)
+            .ok_or_else(|| {
+                Error::MissingData(format!(
+                    "`{}` is missing from the job context; was the matching `Extension` layer added?",
+                    std::any::type_name::<T>()
+                ))
+            })
+    }
+}
+
 impl<S, T> ::tower::Layer<S> for Extension<T>
 where
     T: Clone + Send + Sync + 'static,
@@ -107,4 +125,26 @@ where
         req.context_mut().insert(self.value.clone());
         self.inner.call(req)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_a_present_extension() {
+        let mut req = JobRequest::new(());
+        req.context_mut().insert(42u32);
+
+        let Extension(value) = Extension::<u32>::from_job_request(&req).unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn rejects_a_missing_extension() {
+        let req = JobRequest::new(());
+
+        let err = Extension::<u32>::from_job_request(&req).unwrap_err();
+        assert!(matches!(err, Error::MissingData(_)));
+    }
 }
\ No newline at end of file
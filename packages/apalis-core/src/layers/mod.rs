@@ -0,0 +1,3 @@
+//! Tower layers and middleware shipped with apalis.
+
+pub mod extensions;
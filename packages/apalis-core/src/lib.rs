@@ -0,0 +1,11 @@
+//! Core traits and types shared across the apalis job-processing crates.
+
+pub mod error;
+pub mod extract;
+pub mod layers;
+pub mod middleware;
+pub mod request;
+pub mod storage;
+
+#[doc(inline)]
+pub use extract::{job_fn, FromJobRequest};
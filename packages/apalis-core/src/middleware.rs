@@ -0,0 +1,256 @@
+//! Build ad-hoc middleware from an async function.
+//!
+//! Hand-writing a [`tower::Service`] for every small piece of middleware — a
+//! per-tenant guard, some context enrichment, an ad-hoc rejection rule — means
+//! spelling out `poll_ready`, a `call`, and a pin-projected future each time.
+//! [`from_fn`] removes that ceremony the way axum's `middleware::from_fn` does:
+//! an async closure receives the [`Next`] service and any [`FromJobRequest`]
+//! extractors resolved from the job context, and returns the response.
+//!
+//! Unlike axum, a worker's poll loop calls `poll_ready` on the *outermost*
+//! layer to decide whether to dequeue the next job from storage at all, and
+//! [`FromFnService::poll_ready`] always reports ready without consulting the
+//! wrapped service. So a `from_fn` middleware can reject or short-circuit a
+//! job it already has in hand, but it cannot apply backpressure to dequeuing —
+//! wrapping a concurrency limiter in `from_fn` throttles how many `next.run`
+//! calls are in flight, not how many jobs get claimed from the backend in the
+//! first place. Layers that need to gate dequeuing have to sit outside
+//! `from_fn` and implement `poll_ready` for real.
+
+use std::{future::Future, marker::PhantomData, pin::Pin, task::{Context, Poll}};
+
+use tower::{Layer, Service, ServiceExt};
+
+use crate::{error::Error, extract::FromJobRequest, request::JobRequest};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Create a middleware [`Layer`] from an async function.
+///
+/// The function's leading arguments are the [`FromJobRequest`] extractors it
+/// wants, and its final two are the [`JobRequest`] and the [`Next`] service; it
+/// decides whether (and with what request) to call `next`:
+///
+/// ```rust,no_run
+/// # use std::sync::Arc;
+/// # use apalis_core::{error::Error, layers::extensions::Extension, middleware, request::JobRequest};
+/// # struct Tenants;
+/// # struct MyJob;
+/// # use apalis_core::middleware::Next;
+/// async fn guard<S>(
+///     tenants: Extension<Arc<Tenants>>,
+///     req: JobRequest<MyJob>,
+///     next: Next<S, MyJob>,
+/// ) -> Result<S::Response, Error>
+/// where
+///     S: tower::Service<JobRequest<MyJob>, Error = Error> + Clone + Send + 'static,
+///     S::Response: Send + 'static,
+///     S::Future: Send + 'static,
+/// {
+///     // ...inspect `tenants`, then hand off to the inner service
+///     next.run(req).await
+/// }
+///
+/// let layer = middleware::from_fn(guard);
+/// ```
+pub fn from_fn<F>(f: F) -> FromFn<F> {
+    FromFn { f }
+}
+
+/// A [`Layer`] produced by [`from_fn`].
+#[derive(Debug, Clone, Copy)]
+pub struct FromFn<F> {
+    f: F,
+}
+
+impl<S, F> Layer<S> for FromFn<F>
+where
+    F: Clone,
+{
+    type Service = FromFnService<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FromFnService {
+            inner,
+            f: self.f.clone(),
+        }
+    }
+}
+
+/// The [`Service`] produced by applying a [`FromFn`] layer.
+#[derive(Debug, Clone, Copy)]
+pub struct FromFnService<S, F> {
+    inner: S,
+    f: F,
+}
+
+impl<S, F, J, Args> Service<JobRequest<J>> for FromFnService<S, F>
+where
+    F: FromFnHandler<J, S, Args>,
+    S: Clone,
+{
+    type Response = F::Response;
+    type Error = Error;
+    type Future = BoxFuture<Result<F::Response, Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Always ready: readiness of `inner` is driven through `Next` via
+        // `ServiceExt::oneshot` inside `call`, not here. This means the worker's
+        // poll loop never sees `inner`'s backpressure before dequeuing a job —
+        // see the module docs for why that rules out using `from_fn` to gate
+        // concurrency or rate-limit job dequeuing.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: JobRequest<J>) -> Self::Future {
+        let next = Next {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        };
+        self.f.clone().call(req, next)
+    }
+}
+
+/// The remainder of the middleware stack, handed to a [`from_fn`] closure.
+///
+/// Call [`Next::run`] with a (possibly modified) request to invoke the inner
+/// service.
+pub struct Next<S, J> {
+    inner: S,
+    _marker: PhantomData<fn(J)>,
+}
+
+impl<S: Clone, J> Clone for Next<S, J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S, J> std::fmt::Debug for Next<S, J>
+where
+    S: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Next").field("inner", &self.inner).finish()
+    }
+}
+
+impl<S, J, Res> Next<S, J>
+where
+    S: Service<JobRequest<J>, Response = Res, Error = Error> + Send + 'static,
+    S::Future: Send + 'static,
+    J: Send + 'static,
+    Res: Send + 'static,
+{
+    /// Drive the rest of the stack with `req`.
+    pub fn run(self, req: JobRequest<J>) -> BoxFuture<Result<Res, Error>> {
+        Box::pin(self.inner.oneshot(req))
+    }
+}
+
+/// Async functions that can act as [`from_fn`] middleware.
+///
+/// Implemented for closures whose leading arguments are [`FromJobRequest`]
+/// extractors followed by the [`JobRequest`] and the [`Next`] service, for a
+/// handful of extractor arities.
+pub trait FromFnHandler<J, S, Args>: Clone + Send + Sized + 'static {
+    /// The value produced by the middleware future.
+    type Response: Send + 'static;
+
+    /// Resolve the extractors from `req`, then run the closure.
+    fn call(self, req: JobRequest<J>, next: Next<S, J>) -> BoxFuture<Result<Self::Response, Error>>;
+}
+
+macro_rules! impl_from_fn {
+    ( $( $ty:ident ),* ) => {
+        #[allow(non_snake_case, unused_variables)]
+        impl<F, Fut, J, S, Res, $( $ty, )*> FromFnHandler<J, S, ( $( $ty, )* )> for F
+        where
+            F: FnOnce($( $ty, )* JobRequest<J>, Next<S, J>) -> Fut + Clone + Send + 'static,
+            Fut: Future<Output = Result<Res, Error>> + Send + 'static,
+            J: Send + 'static,
+            Res: Send + 'static,
+            $( $ty: FromJobRequest<J> + Send + 'static, )*
+        {
+            type Response = Res;
+
+            fn call(
+                self,
+                req: JobRequest<J>,
+                next: Next<S, J>,
+            ) -> BoxFuture<Result<Self::Response, Error>> {
+                $(
+                    let $ty = match $ty::from_job_request(&req) {
+                        Ok(value) => value,
+                        Err(err) => return Box::pin(async move { Err(err) }),
+                    };
+                )*
+                Box::pin(self($( $ty, )* req, next))
+            }
+        }
+    };
+}
+
+impl_from_fn!();
+impl_from_fn!(T1);
+impl_from_fn!(T1, T2);
+impl_from_fn!(T1, T2, T3);
+impl_from_fn!(T1, T2, T3, T4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::extensions::Extension;
+    use tower::{service_fn, ServiceExt};
+
+    fn echo() -> impl Service<JobRequest<u32>, Response = u32, Error = Error> + Clone {
+        service_fn(|req: JobRequest<u32>| async move { Ok::<_, Error>(req.into_inner()) })
+    }
+
+    #[tokio::test]
+    async fn from_fn_resolves_extractors_and_runs_next() {
+        async fn guard<S>(
+            tenant: Extension<u32>,
+            req: JobRequest<u32>,
+            next: Next<S, u32>,
+        ) -> Result<u32, Error>
+        where
+            S: Service<JobRequest<u32>, Response = u32, Error = Error> + Send + 'static,
+            S::Future: Send + 'static,
+        {
+            assert_eq!(tenant.0, 7);
+            next.run(req).await
+        }
+
+        let mut req = JobRequest::new(5u32);
+        req.context_mut().insert(7u32);
+
+        let svc = from_fn(guard).layer(echo());
+        let out = svc.oneshot(req).await.unwrap();
+        assert_eq!(out, 5);
+    }
+
+    #[tokio::test]
+    async fn from_fn_rejects_when_an_extractor_is_missing() {
+        async fn guard<S>(
+            _tenant: Extension<u32>,
+            req: JobRequest<u32>,
+            next: Next<S, u32>,
+        ) -> Result<u32, Error>
+        where
+            S: Service<JobRequest<u32>, Response = u32, Error = Error> + Send + 'static,
+            S::Future: Send + 'static,
+        {
+            next.run(req).await
+        }
+
+        let req = JobRequest::new(5u32);
+
+        let svc = from_fn(guard).layer(echo());
+        let err = svc.oneshot(req).await.unwrap_err();
+        assert!(matches!(err, Error::MissingData(_)));
+    }
+}
@@ -0,0 +1,10 @@
+//! Optional instrumentation layers for apalis workers.
+//!
+//! Each layer lives behind a feature flag so that applications only pull in the
+//! metrics backend they actually export to.
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+#[cfg(feature = "opentelemetry")]
+pub mod opentelemetry;
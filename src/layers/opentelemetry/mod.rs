@@ -0,0 +1,211 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use apalis_core::{error::Error, request::Request, storage::Job};
+use futures::Future;
+use opentelemetry::{
+    metrics::{Histogram, MeterProvider, UpDownCounter},
+    global, KeyValue,
+};
+use pin_project_lite::pin_project;
+use tower::{Layer, Service};
+
+/// A layer to record worker metrics through the OpenTelemetry metrics API.
+///
+/// This is the OTLP-friendly sibling of [`PrometheusLayer`]: instead of emitting
+/// through the `metrics` facade it records against an OpenTelemetry [`Meter`], so
+/// the timings can be exported to an OpenTelemetry collector over OTLP.
+///
+/// [`PrometheusLayer`]: crate::layers::prometheus::PrometheusLayer
+/// [`Meter`]: opentelemetry::metrics::Meter
+#[derive(Debug, Default)]
+pub struct OpenTelemetryLayer {
+    // stored at creation time, mirroring PrometheusLayer; could instead be
+    // derived from the job type via a phantom data field and its NAME constant
+    job_name: String,
+}
+
+impl OpenTelemetryLayer {
+    /// Create a new OpenTelemetryLayer that instruments metrics with a label of the specified job
+    /// name
+    pub fn new(job_name: &str) -> Self {
+        Self {
+            job_name: job_name.to_string(),
+        }
+    }
+}
+
+impl<S> Layer<S> for OpenTelemetryLayer {
+    type Service = OpenTelemetryService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        let meter = global::meter_provider().meter("apalis");
+
+        let duration = meter
+            .f64_histogram("apalis_request_duration_seconds")
+            .init();
+        let in_flight = meter.i64_up_down_counter("apalis_jobs_in_flight").init();
+
+        OpenTelemetryService {
+            service,
+            job_name: self.job_name.clone(),
+            duration,
+            in_flight,
+        }
+    }
+}
+
+/// This service implements the OpenTelemetry metric collection behavior
+#[derive(Clone)]
+pub struct OpenTelemetryService<S> {
+    service: S,
+    job_name: String,
+    duration: Histogram<f64>,
+    in_flight: UpDownCounter<i64>,
+}
+
+// manually implement debug because the metric structs do not have a Debug implementation
+impl<S> std::fmt::Debug for OpenTelemetryService<S>
+where
+    S: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenTelemetryService")
+            .field("service", &self.service)
+            .field("job_name", &self.job_name)
+            .finish()
+    }
+}
+
+impl<S, J, F, Res> Service<Request<J>> for OpenTelemetryService<S>
+where
+    S: Service<Request<J>, Response = Res, Error = Error, Future = F>,
+    F: Future<Output = Result<Res, Error>> + 'static,
+    J: Job,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<F>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<J>) -> Self::Future {
+        let start = Instant::now();
+        let labels = [KeyValue::new("job_name", self.job_name.clone())];
+        let req = self.service.call(request);
+        ResponseFuture {
+            inner: req,
+            start,
+            labels: labels.clone(),
+            duration: self.duration.clone(),
+            // stays in flight until this guard is dropped, so a cancelled or
+            // dropped future can never skew the up/down counter upward
+            in_flight: InFlightGuard::new(self.in_flight.clone(), labels),
+        }
+    }
+}
+
+/// Keeps the in-flight up/down counter balanced: it adds `1` on construction
+/// and `-1` on drop, regardless of whether the job future completes or is
+/// cancelled before completion.
+struct InFlightGuard {
+    counter: UpDownCounter<i64>,
+    labels: [KeyValue; 1],
+}
+
+impl InFlightGuard {
+    fn new(counter: UpDownCounter<i64>, labels: [KeyValue; 1]) -> Self {
+        counter.add(1, &labels);
+        Self { counter, labels }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.counter.add(-1, &self.labels);
+    }
+}
+
+pin_project! {
+    /// Response for the OpenTelemetry service
+    pub struct ResponseFuture<F> {
+        #[pin]
+        pub(crate) inner: F,
+        pub(crate) start: Instant,
+        pub(crate) labels: [KeyValue; 1],
+        pub(crate) duration: Histogram<f64>,
+        pub(crate) in_flight: InFlightGuard,
+    }
+}
+
+impl<Fut, Res> Future for ResponseFuture<Fut>
+where
+    Fut: Future<Output = Result<Res, Error>>,
+{
+    type Output = Result<Res, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let response = futures::ready!(this.inner.poll(cx));
+
+        let latency = this.start.elapsed().as_secs_f64();
+
+        this.duration.record(latency, this.labels);
+        // the in-flight counter is decremented when `in_flight` is dropped
+
+        Poll::Ready(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::{
+        metrics::{data, InMemoryMetricExporter, PeriodicReader, SdkMeterProvider},
+        runtime,
+    };
+
+    fn in_flight_total(exporter: &InMemoryMetricExporter) -> i64 {
+        let exported = exporter.get_finished_metrics().unwrap();
+        let latest = exported.last().expect("metrics were exported");
+        for scope in &latest.scope_metrics {
+            for metric in &scope.metrics {
+                if metric.name == "apalis_jobs_in_flight" {
+                    if let Some(sum) = metric.data.as_any().downcast_ref::<data::Sum<i64>>() {
+                        return sum.data_points.iter().map(|dp| dp.value).sum();
+                    }
+                }
+            }
+        }
+        0
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn in_flight_counter_is_balanced_after_a_dropped_future() {
+        let exporter = InMemoryMetricExporter::default();
+        let reader = PeriodicReader::builder(exporter.clone(), runtime::Tokio).build();
+        let provider = SdkMeterProvider::builder().with_reader(reader).build();
+
+        let counter = provider
+            .meter("apalis")
+            .i64_up_down_counter("apalis_jobs_in_flight")
+            .init();
+        let labels = [KeyValue::new("job_name", "test")];
+
+        // a job is now in flight
+        let guard = InFlightGuard::new(counter.clone(), labels.clone());
+        provider.force_flush().unwrap();
+        assert_eq!(in_flight_total(&exporter), 1);
+
+        // drop the guard without the future ever completing (e.g. cancellation)
+        drop(guard);
+        provider.force_flush().unwrap();
+        assert_eq!(in_flight_total(&exporter), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    marker::PhantomData,
     pin::Pin,
     task::{Context, Poll},
     time::Instant,
@@ -6,17 +7,34 @@ use std::{
 
 use apalis_core::{error::Error, request::Request, storage::Job};
 use futures::Future;
-use metrics::{Counter, Histogram};
+use metrics::{Counter, Gauge, Histogram};
 use pin_project_lite::pin_project;
 use tower::{Layer, Service};
 
 /// A layer to support prometheus metrics
-#[derive(Debug, Default)]
-pub struct PrometheusLayer {
-    // storing this here at creation time, but we could potentially get
-    // the job type and name by capturing the job type in a phantom data field
-    // and grabbing the typename or NAME from it
+///
+/// The `job_name` label can be supplied in two ways. The typed path
+/// ([`PrometheusLayer::for_job`]) captures the job type in a [`PhantomData`]
+/// field and reads its [`Job::NAME`] constant, so the label can never drift
+/// from the type it describes; prefer it whenever the job type is known at the
+/// call site. The string path ([`PrometheusLayer::new`]) remains for the
+/// dynamic cases where the name isn't known at the type level.
+///
+/// There is no `WorkerBuilder` in this crate to wire the typed path into by
+/// default; callers add either layer explicitly via [`tower::Layer`].
+#[derive(Debug)]
+pub struct PrometheusLayer<J = ()> {
     job_name: String,
+    _job: PhantomData<fn(J)>,
+}
+
+impl Default for PrometheusLayer {
+    fn default() -> Self {
+        Self {
+            job_name: String::new(),
+            _job: PhantomData,
+        }
+    }
 }
 
 impl PrometheusLayer {
@@ -25,23 +43,46 @@ impl PrometheusLayer {
     pub fn new(job_name: &str) -> Self {
         Self {
             job_name: job_name.to_string(),
+            _job: PhantomData,
         }
     }
 }
 
-impl<S> Layer<S> for PrometheusLayer {
+impl<J: Job> PrometheusLayer<J> {
+    /// Create a PrometheusLayer whose `job_name` label is taken from the job
+    /// type's [`Job::NAME`], removing any chance of a mis-typed label string.
+    pub fn for_job() -> Self {
+        Self {
+            job_name: J::NAME.to_string(),
+            _job: PhantomData,
+        }
+    }
+}
+
+impl<S, J> Layer<S> for PrometheusLayer<J> {
     type Service = PrometheusService<S>;
 
     fn layer(&self, service: S) -> Self::Service {
+        // `outcome` is a fixed, small set (success/failure) so every handle can
+        // be resolved once here rather than re-looked-up by name on each poll.
+        let success = [
+            ("job_name", self.job_name.clone()),
+            ("outcome", "success".to_owned()),
+        ];
+        let failure = [
+            ("job_name", self.job_name.clone()),
+            ("outcome", "failure".to_owned()),
+        ];
         let labels = [("job_name", self.job_name.clone())];
 
-        let req_counter = metrics::counter!("apalis_requests_total", &labels);
-        let req_histogram = metrics::histogram!("apalis_request_duration_seconds", &labels);
-
         PrometheusService {
             service,
-            req_counter,
-            req_histogram,
+            job_name: self.job_name.clone(),
+            success_counter: metrics::counter!("apalis_requests_total", &success),
+            failure_counter: metrics::counter!("apalis_requests_total", &failure),
+            success_histogram: metrics::histogram!("apalis_request_duration_seconds", &success),
+            failure_histogram: metrics::histogram!("apalis_request_duration_seconds", &failure),
+            in_progress: metrics::gauge!("apalis_jobs_in_progress", &labels),
         }
     }
 }
@@ -50,8 +91,12 @@ impl<S> Layer<S> for PrometheusLayer {
 #[derive(Clone)]
 pub struct PrometheusService<S> {
     service: S,
-    req_counter: Counter,
-    req_histogram: Histogram,
+    job_name: String,
+    success_counter: Counter,
+    failure_counter: Counter,
+    success_histogram: Histogram,
+    failure_histogram: Histogram,
+    in_progress: Gauge,
 }
 
 // manually implement debug because the metric structs do not have a Debug implementation
@@ -62,6 +107,7 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PrometheusService")
             .field("service", &self.service)
+            .field("job_name", &self.job_name)
             .finish()
     }
 }
@@ -86,20 +132,52 @@ where
         ResponseFuture {
             inner: req,
             start,
-            req_counter: self.req_counter.clone(),
-            req_histogram: self.req_histogram.clone(),
+            success_counter: self.success_counter.clone(),
+            failure_counter: self.failure_counter.clone(),
+            success_histogram: self.success_histogram.clone(),
+            failure_histogram: self.failure_histogram.clone(),
+            // a new job is in flight until this guard is dropped, which happens
+            // whether the future completes, is cancelled, or is dropped early
+            in_progress: InProgressGuard::new(self.in_progress.clone()),
         }
     }
 }
 
+/// Keeps the `apalis_jobs_in_progress` gauge balanced: it increments on
+/// construction and decrements on drop, so a cancelled or dropped job future
+/// can never leave the gauge leaked upward.
+///
+/// This is deliberately move-only: a `Clone` would copy the handle without
+/// re-incrementing, so the extra guard's drop would decrement an increment that
+/// never happened and leak the gauge downward.
+pub(crate) struct InProgressGuard {
+    gauge: Gauge,
+}
+
+impl InProgressGuard {
+    fn new(gauge: Gauge) -> Self {
+        gauge.increment(1.0);
+        Self { gauge }
+    }
+}
+
+impl Drop for InProgressGuard {
+    fn drop(&mut self) {
+        self.gauge.decrement(1.0);
+    }
+}
+
 pin_project! {
     /// Response for prometheus service
     pub struct ResponseFuture<F> {
         #[pin]
         pub(crate) inner: F,
         pub(crate) start: Instant,
-        pub(crate) req_counter: Counter,
-        pub(crate) req_histogram: Histogram,
+        pub(crate) success_counter: Counter,
+        pub(crate) failure_counter: Counter,
+        pub(crate) success_histogram: Histogram,
+        pub(crate) failure_histogram: Histogram,
+        pub(crate) in_progress: InProgressGuard,
     }
 }
 
@@ -115,9 +193,111 @@ where
 
         let latency = this.start.elapsed().as_secs_f64();
 
-        this.req_counter.increment(1);
-        this.req_histogram.record(latency);
+        // branch on the outcome so dashboards can tell successes from failures,
+        // recording into the pre-resolved handle for the matching bucket
+        match &response {
+            Ok(_) => {
+                this.success_counter.increment(1);
+                this.success_histogram.record(latency);
+            }
+            Err(_) => {
+                this.failure_counter.increment(1);
+                this.failure_histogram.record(latency);
+            }
+        }
+
+        // the in-flight gauge is decremented when `in_progress` is dropped
 
         Poll::Ready(response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshot};
+    use tower::service_fn;
+
+    struct TestJob;
+
+    impl Job for TestJob {
+        const NAME: &'static str = "test_job";
+    }
+
+    fn gauge_value(snapshot: &Snapshot, name: &str) -> f64 {
+        for (key, _, _, value) in snapshot.clone().into_vec() {
+            if key.key().name() == name {
+                if let DebugValue::Gauge(v) = value {
+                    return v.into_inner();
+                }
+            }
+        }
+        0.0
+    }
+
+    fn counter_value(snapshot: &Snapshot, name: &str, outcome: &str) -> u64 {
+        for (key, _, _, value) in snapshot.clone().into_vec() {
+            let matches_outcome = key
+                .key()
+                .labels()
+                .any(|label| label.key() == "outcome" && label.value() == outcome);
+            if key.key().name() == name && matches_outcome {
+                if let DebugValue::Counter(v) = value {
+                    return v;
+                }
+            }
+        }
+        0
+    }
+
+    #[test]
+    fn in_progress_gauge_is_balanced_after_a_dropped_future() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let gauge =
+                metrics::gauge!("apalis_jobs_in_progress", &[("job_name", "test".to_owned())]);
+
+            // a job is now in progress
+            let guard = InProgressGuard::new(gauge);
+            assert_eq!(
+                gauge_value(&snapshotter.snapshot(), "apalis_jobs_in_progress"),
+                1.0
+            );
+
+            // drop the guard without the future ever completing (e.g. cancellation)
+            drop(guard);
+            assert_eq!(
+                gauge_value(&snapshotter.snapshot(), "apalis_jobs_in_progress"),
+                0.0
+            );
+        });
+    }
+
+    #[test]
+    fn a_failing_job_only_increments_the_failure_handles() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let inner = service_fn(|_req: Request<TestJob>| async {
+                Err::<(), Error>(Error::MissingData("boom".to_owned()))
+            });
+            let mut service = PrometheusLayer::<TestJob>::for_job().layer(inner);
+
+            let result = futures::executor::block_on(service.call(Request::new(TestJob)));
+            assert!(result.is_err());
+
+            let snapshot = snapshotter.snapshot();
+            assert_eq!(
+                counter_value(&snapshot, "apalis_requests_total", "failure"),
+                1
+            );
+            assert_eq!(
+                counter_value(&snapshot, "apalis_requests_total", "success"),
+                0
+            );
+        });
+    }
+}
@@ -0,0 +1,3 @@
+//! apalis — background job processing for Rust.
+
+pub mod layers;